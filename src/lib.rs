@@ -6,6 +6,7 @@
 //! `pub fn insert(&mut self, k: K, v: V) -> Option<V>`
 //! `pub fn get<Q: ?Sized>(&self, k: &Q) -> Option<&V> where K: Borrow<Q>`
 //! `pub fn get_mut<Q: ?Sized>(&mut self, k: &Q) -> Option<&mut V> where K: Borrow<Q>`
+//! `pub fn remove<Q: ?Sized>(&mut self, k: &Q) -> Option<V> where K: Borrow<Q>`
 //! `pub fn iter(&self) -> Iter<'_, K, V>`
 //!
 //! Because the similarity between `HashMap`, `BTreeMap`, and other similar types is structural,
@@ -92,6 +93,31 @@
 //! assert_eq!(ranking_by_weight!(num_weights), VecDeque::from([(1, OrderedFloat(2.0)), (3, OrderedFloat(0.8)), (2, OrderedFloat(0.4))]));
 //! ```
 //!
+//! Sometimes you want each key's share of the total rather than its raw count or weight.
+//! Use `proportion!`/`proportion_ref!` (and `proportion_by_weight!`) to get that share directly,
+//! and `ranking_normalized!`/`ranking_normalized_by_weight!` to rank keys by their share.
+//!
+//! ```
+//! use histogram_macros::*;
+//! use std::collections::HashMap;
+//! use std::collections::VecDeque;
+//!
+//! let mut hist = HashMap::new();
+//! bump!(hist, "a");
+//! bump!(hist, "b");
+//! bump!(hist, "a");
+//! bump!(hist, "b");
+//! bump!(hist, "b");
+//!
+//! assert_eq!(proportion!(hist, "a"), 0.4);
+//! assert_eq!(proportion_ref!(hist, "b"), 0.6);
+//! assert_eq!(ranking_normalized!(hist), VecDeque::from([("b", 0.6), ("a", 0.4)]));
+//!
+//! // An empty histogram reports a proportion of 0.0 instead of dividing by zero.
+//! let empty: HashMap<&str, usize> = HashMap::new();
+//! assert_eq!(proportion!(empty, "a"), 0.0);
+//! ```
+//!
 //! Building a histogram from a sequence of values is a common pattern. You can use the
 //! `collect_from_into!`, `collect_from_ref_into!`, `collect_from_by_into!`, and
 //! `collect_from_ref_by_into!` macros to abstract this pattern.
@@ -125,6 +151,58 @@
 //! }
 //! ```
 //!
+//! Counting contiguous n-grams over a sequence is another common pattern. Use
+//! `collect_ngrams_into!` (or `collect_ngrams_ref_into!` when iterating over borrowed items)
+//! to slide a window of length `n` across an iterator and bump a `Vec<T>` key for each window.
+//!
+//! ```
+//! use histogram_macros::*;
+//! use std::collections::HashMap;
+//! use std::collections::VecDeque;
+//!
+//! let mut bigrams = HashMap::new();
+//! collect_ngrams_into!(["a", "b", "a", "b", "c"].iter().copied(), 2, bigrams);
+//! assert_eq!(count!(bigrams, vec!["a", "b"]), 2);
+//! assert_eq!(count!(bigrams, vec!["b", "a"]), 1);
+//! assert_eq!(count!(bigrams, vec!["b", "c"]), 1);
+//!
+//! // A sequence shorter than `n` contributes no entries.
+//! let mut none = HashMap::new();
+//! collect_ngrams_into!(["a"].iter().copied(), 2, none);
+//! assert_eq!(total!(none), 0);
+//! ```
+//!
+//! Two histograms built separately (e.g. one per worker, or snapshots taken at different times)
+//! can be combined with `merge_into!` and `subtract_from!` (and their `_by_weight` counterparts
+//! for the float path). `merge_into!(dest, src)` adds every count in `src` into `dest`.
+//! `subtract_from!(dest, src)` removes every count in `src` from `dest`, saturating at zero and
+//! dropping any key whose count reaches zero so stale entries don't linger.
+//!
+//! ```
+//! use histogram_macros::*;
+//! use std::collections::HashMap;
+//!
+//! let mut totals = HashMap::new();
+//! bump!(totals, "a");
+//! bump!(totals, "a");
+//! bump!(totals, "b");
+//!
+//! let mut other = HashMap::new();
+//! bump!(other, "a");
+//! bump!(other, "c");
+//!
+//! merge_into!(totals, other);
+//! assert_eq!(count!(totals, "a"), 3);
+//! assert_eq!(count!(totals, "b"), 1);
+//! assert_eq!(count!(totals, "c"), 1);
+//!
+//! subtract_from!(totals, other);
+//! assert_eq!(count!(totals, "a"), 2);
+//! assert_eq!(count!(totals, "b"), 1);
+//! // "c" dropped out entirely rather than lingering at a count of 0.
+//! assert_eq!(totals.contains_key("c"), false);
+//! ```
+//!
 //! Alternatively, one can use histogram_struct! to create a custom histogram data type.
 //!
 //! ```
@@ -160,6 +238,53 @@
 //! assert_eq!(2, hist.mode().unwrap());
 //! assert_eq!(zeros + ones + twos, hist.total_count());
 //! ```
+//!
+//! Types generated by `histogram_struct!` implement `FromIterator`, `Extend`, and borrowed
+//! `IntoIterator`, so they participate in ordinary iterator pipelines.
+//!
+//! ```
+//! use histogram_macros::*;
+//!
+//! let words = ["a", "b", "a", "b", "b"];
+//! let mut hist: HashHistogram<&str> = words.iter().collect();
+//! assert_eq!(hist.count(&"a"), 2);
+//! assert_eq!(hist.count(&"b"), 3);
+//!
+//! hist.extend(["a", "c"]);
+//! assert_eq!(hist.count(&"a"), 3);
+//! assert_eq!(hist.count(&"c"), 1);
+//!
+//! let total: usize = (&hist).into_iter().map(|(_, count)| count).sum();
+//! assert_eq!(total, hist.total_count());
+//! ```
+//!
+//! To pick a different hasher for the backing `HashMap` (for example, a faster non-cryptographic
+//! hasher for large-vocabulary counting), pass it as an extra argument to `histogram_struct!`.
+//! The generated type stays generic over the hasher, and gains `with_hasher`, `with_capacity`,
+//! and `with_capacity_and_hasher` constructors alongside the usual `new`.
+//!
+//! ```
+//! use histogram_macros::histogram_struct;
+//!
+//! use std::hash::Hash;
+//! use std::collections::{HashSet, HashMap};
+//! use std::collections::hash_map::{Iter, RandomState};
+//!
+//! histogram_struct!{CustomHashHistogram, CustomHashHistKey, HashMap, HashSet, Iter, Hash, RandomState}
+//!
+//! let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::new();
+//! hist.bump(&1);
+//! hist.bump(&1);
+//! assert_eq!(hist.count(&1), 2);
+//!
+//! let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::with_capacity(16);
+//! hist.bump(&2);
+//! assert_eq!(hist.count(&2), 1);
+//!
+//! let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::with_hasher(RandomState::new());
+//! hist.bump(&3);
+//! assert_eq!(hist.count(&3), 1);
+//! ```
 
 
 //    Copyright 2022, Gabriel J. Ferrer
@@ -184,19 +309,14 @@ use std::hash::Hash;
 use std::collections::{HashSet,HashMap};
 use std::collections::hash_map::Iter;
 
+// Shared by every `histogram_struct!` arm: the inherent API plus the collection trait impls are
+// identical no matter how the backing map is constructed, so besides forwarding its label-set and
+// iterator types, each arm only needs to supply the `impl` generics, the concrete `Self` type, and
+// how `Default` builds one from scratch.
 #[macro_export]
-macro_rules! histogram_struct {
-    ($name:ident, $keyname:ident, $inner:ident, $labelset:ident, $iter:ident, $constraint:ident) => {
-        pub trait $keyname: std::fmt::Debug + $constraint + Clone + Eq {}
-        impl <T: std::fmt::Debug + $constraint + Clone + Eq> $keyname for T {}
-
-        pub struct $name<T:$keyname> {
-            histogram: $inner<T, usize>
-        }
-
-        impl <T:$keyname> $name<T> {
-            pub fn new() -> Self { $name { histogram: $inner::new()}}
-
+macro_rules! histogram_struct_body {
+    ($selfty:ty, $labelset:ident, $iter:ident, $default_expr:expr, $($gen:tt)*) => {
+        impl <$($gen)*> $selfty {
             pub fn bump(&mut self, item: &T) {
                 self.bump_by(item, 1);
             }
@@ -241,7 +361,100 @@ macro_rules! histogram_struct {
             pub fn total_count(&self) -> usize {
                 self.iter().map(|(_,value)| value).sum()
             }
+
+            pub fn proportion(&self, item: &T) -> f64 {
+                let total = self.total_count() as f64;
+                if total == 0.0 {0.0} else {self.count(item) as f64 / total}
+            }
+        }
+
+        impl <$($gen)*> Default for $selfty {
+            fn default() -> Self {
+                $default_expr
+            }
+        }
+
+        impl <$($gen)*> FromIterator<T> for $selfty {
+            fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+                let mut result = Self::default();
+                result.extend(iter);
+                result
+            }
+        }
+
+        impl <'a, $($gen)*> FromIterator<&'a T> for $selfty {
+            fn from_iter<I: IntoIterator<Item=&'a T>>(iter: I) -> Self {
+                let mut result = Self::default();
+                result.extend(iter);
+                result
+            }
+        }
+
+        impl <$($gen)*> Extend<T> for $selfty {
+            fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+                for item in iter {
+                    self.bump(&item);
+                }
+            }
+        }
+
+        impl <'a, $($gen)*> Extend<&'a T> for $selfty {
+            fn extend<I: IntoIterator<Item=&'a T>>(&mut self, iter: I) {
+                for item in iter {
+                    self.bump(item);
+                }
+            }
+        }
+
+        impl <'a, $($gen)*> IntoIterator for &'a $selfty {
+            type Item = (&'a T, &'a usize);
+            type IntoIter = $iter<'a, T, usize>;
+
+            fn into_iter(self) -> Self::IntoIter {
+                self.iter()
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! histogram_struct {
+    ($name:ident, $keyname:ident, $inner:ident, $labelset:ident, $iter:ident, $constraint:ident) => {
+        pub trait $keyname: std::fmt::Debug + $constraint + Clone + Eq {}
+        impl <T: std::fmt::Debug + $constraint + Clone + Eq> $keyname for T {}
+
+        pub struct $name<T:$keyname> {
+            histogram: $inner<T, usize>
         }
+
+        impl <T:$keyname> $name<T> {
+            pub fn new() -> Self { $name { histogram: $inner::new()}}
+        }
+
+        $crate::histogram_struct_body!{$name<T>, $labelset, $iter, Self::new(), T:$keyname}
+    };
+
+    ($name:ident, $keyname:ident, $inner:ident, $labelset:ident, $iter:ident, $constraint:ident, $hasher:ty) => {
+        pub trait $keyname: std::fmt::Debug + $constraint + Clone + Eq {}
+        impl <T: std::fmt::Debug + $constraint + Clone + Eq> $keyname for T {}
+
+        pub struct $name<T:$keyname, S = $hasher> {
+            histogram: $inner<T, usize, S>
+        }
+
+        impl <T:$keyname> $name<T, $hasher> {
+            pub fn new() -> Self { $name { histogram: $inner::with_hasher(<$hasher>::default())}}
+        }
+
+        impl <T:$keyname, S: std::hash::BuildHasher + Default> $name<T, S> {
+            pub fn with_hasher(hasher: S) -> Self { $name { histogram: $inner::with_hasher(hasher)}}
+
+            pub fn with_capacity(capacity: usize) -> Self { $name { histogram: $inner::with_capacity_and_hasher(capacity, S::default())}}
+
+            pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self { $name { histogram: $inner::with_capacity_and_hasher(capacity, hasher)}}
+        }
+
+        $crate::histogram_struct_body!{$name<T, S>, $labelset, $iter, Self::with_hasher(S::default()), T:$keyname, S: std::hash::BuildHasher + Default}
     }
 }
 
@@ -355,6 +568,37 @@ macro_rules! mode_by_weight {
     }
 }
 
+#[macro_export]
+macro_rules! proportion_skeleton {
+    ($count:expr, $total:expr) => {
+        {
+            let total = $total as f64;
+            if total == 0.0 {0.0} else {$count as f64 / total}
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! proportion_ref {
+    ($d:expr, $k:expr) => {
+        proportion_skeleton!(count_ref!($d, $k), total!($d))
+    }
+}
+
+#[macro_export]
+macro_rules! proportion {
+    ($d:expr, $k:expr) => {
+        proportion_ref!($d, &$k)
+    }
+}
+
+#[macro_export]
+macro_rules! proportion_by_weight {
+    ($d:expr, $k:expr) => {
+        proportion_skeleton!(weight!($d, $k), total_weight!($d))
+    }
+}
+
 #[macro_export]
 macro_rules! ranking_skeleton {
     ($seq:expr) => {
@@ -380,6 +624,33 @@ macro_rules! ranking_by_weight {
     }
 }
 
+#[macro_export]
+macro_rules! ranking_normalized_skeleton {
+    ($d:expr, $total:expr) => {
+        {
+            let total = $total;
+            ranking_skeleton!($d.iter().map(|(t, n)| (ordered_float::OrderedFloat(proportion_skeleton!(*n, total)), t.clone())))
+                .drain(..)
+                .map(|(t, share)| (t, share.into_inner()))
+                .collect::<VecDeque<_>>()
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! ranking_normalized {
+    ($d:expr) => {
+        ranking_normalized_skeleton!($d, total!($d))
+    }
+}
+
+#[macro_export]
+macro_rules! ranking_normalized_by_weight {
+    ($d:expr) => {
+        ranking_normalized_skeleton!($d, total_weight!($d))
+    }
+}
+
 #[macro_export]
 macro_rules! collect_from_skeleton {
     ($iter:expr, $d:expr, $b:ident) => {
@@ -433,6 +704,96 @@ macro_rules! collect_from_ref_by_into {
     }
 }
 
+#[macro_export]
+macro_rules! collect_ngrams_into {
+    ($iter:expr, $n:expr, $d:expr) => {
+        {
+            let mut window: VecDeque<_> = VecDeque::new();
+            for item in $iter {
+                window.push_back(item);
+                if window.len() == $n {
+                    let key: Vec<_> = window.iter().cloned().collect();
+                    bump!($d, key);
+                    window.pop_front();
+                }
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! collect_ngrams_ref_into {
+    ($iter:expr, $n:expr, $d:expr) => {
+        {
+            let mut window: VecDeque<_> = VecDeque::new();
+            for item in $iter {
+                window.push_back(item);
+                if window.len() == $n {
+                    let key: Vec<_> = window.iter().map(|t| (*t).clone()).collect();
+                    bump!($d, key);
+                    window.pop_front();
+                }
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! merge_into_skeleton {
+    ($dest:expr, $src:expr) => {
+        for (k, v) in $src.iter() {
+            bump_skeleton!($dest, k, k.clone(), *v);
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! merge_into {
+    ($dest:expr, $src:expr) => {
+        merge_into_skeleton!($dest, $src)
+    }
+}
+
+#[macro_export]
+macro_rules! merge_by_weight_into {
+    ($dest:expr, $src:expr) => {
+        merge_into_skeleton!($dest, $src)
+    }
+}
+
+#[macro_export]
+macro_rules! subtract_from_skeleton {
+    ($dest:expr, $src:expr, $sub:expr, $zero:expr) => {
+        for (k, v) in $src.iter() {
+            match $dest.get_mut(k) {
+                None => {}
+                Some(count) => {
+                    let next = $sub(*count, *v);
+                    if next <= $zero {
+                        $dest.remove(k);
+                    } else {
+                        *count = next;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[macro_export]
+macro_rules! subtract_from {
+    ($dest:expr, $src:expr) => {
+        subtract_from_skeleton!($dest, $src, usize::saturating_sub, 0)
+    }
+}
+
+#[macro_export]
+macro_rules! subtract_by_weight_from {
+    ($dest:expr, $src:expr) => {
+        subtract_from_skeleton!($dest, $src, |a: f64, b: f64| (a - b).max(0.0), 0.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -498,6 +859,33 @@ mod tests {
         println!("{:?}", r);
     }
 
+    #[test]
+    fn test_proportion() {
+        let mut hist = HashMap::new();
+        bump!(hist, "a");
+        bump!(hist, "b");
+        bump!(hist, "a");
+        bump!(hist, "b");
+        bump!(hist, "b");
+        assert_eq!(proportion!(hist, "a"), 0.4);
+        assert_eq!(proportion_ref!(hist, "b"), 0.6);
+        assert_eq!(proportion!(hist, "c"), 0.0);
+        assert_eq!(ranking_normalized!(hist), VecDeque::from([("b", 0.6), ("a", 0.4)]));
+
+        let empty: HashMap<&str, usize> = HashMap::new();
+        assert_eq!(proportion!(empty, "a"), 0.0);
+    }
+
+    #[test]
+    fn test_proportion_by_weight() {
+        let mut hist = HashMap::new();
+        bump_by!(hist, 1, 1.5);
+        bump_by!(hist, 2, 2.5);
+        assert_eq!(proportion_by_weight!(hist, 1), 0.375);
+        assert_eq!(proportion_by_weight!(hist, 2), 0.625);
+        assert_eq!(ranking_normalized_by_weight!(hist), VecDeque::from([(2, 0.625), (1, 0.375)]));
+    }
+
     #[test]
     fn test_collect() {
         let h = collect_from_into!([100, 200, -100, 200, 300, 200, 100, 200, 100, 300]
@@ -511,6 +899,80 @@ mod tests {
         assert_eq!(format!("{:?}", h), r#"{"a": 3, "b": 4, "c": 2}"#);
     }
 
+    #[test]
+    fn test_ngrams() {
+        let mut bigrams = HashMap::new();
+        collect_ngrams_into!(["a", "b", "a", "b", "c"].iter().copied(), 2, bigrams);
+        assert_eq!(count!(bigrams, vec!["a", "b"]), 2);
+        assert_eq!(count!(bigrams, vec!["b", "a"]), 1);
+        assert_eq!(count!(bigrams, vec!["b", "c"]), 1);
+        assert_eq!(total!(bigrams), 4);
+
+        let mut trigrams = HashMap::new();
+        collect_ngrams_ref_into!(["a", "b", "a", "b", "c"].iter(), 3, trigrams);
+        assert_eq!(count!(trigrams, vec!["a", "b", "a"]), 1);
+        assert_eq!(count!(trigrams, vec!["b", "a", "b"]), 1);
+        assert_eq!(count!(trigrams, vec!["a", "b", "c"]), 1);
+
+        let mut unigrams = HashMap::new();
+        collect_ngrams_into!(["a", "b", "a"].iter().copied(), 1, unigrams);
+        assert_eq!(count!(unigrams, vec!["a"]), 2);
+        assert_eq!(count!(unigrams, vec!["b"]), 1);
+
+        let mut too_short = HashMap::new();
+        collect_ngrams_into!(["a", "b"].iter().copied(), 5, too_short);
+        assert_eq!(total!(too_short), 0);
+    }
+
+    #[test]
+    fn test_merge_and_subtract() {
+        let mut totals = HashMap::new();
+        bump!(totals, "a");
+        bump!(totals, "a");
+        bump!(totals, "b");
+
+        let mut other = HashMap::new();
+        bump!(other, "a");
+        bump!(other, "c");
+
+        merge_into!(totals, other);
+        assert_eq!(count!(totals, "a"), 3);
+        assert_eq!(count!(totals, "b"), 1);
+        assert_eq!(count!(totals, "c"), 1);
+
+        subtract_from!(totals, other);
+        assert_eq!(count!(totals, "a"), 2);
+        assert_eq!(count!(totals, "b"), 1);
+        assert!(!totals.contains_key("c"));
+
+        // Subtracting a count down to zero removes the key.
+        subtract_from!(totals, other);
+        assert_eq!(count!(totals, "a"), 1);
+        subtract_from!(totals, other);
+        assert_eq!(count!(totals, "a"), 0);
+        assert!(!totals.contains_key("a"));
+    }
+
+    #[test]
+    fn test_merge_and_subtract_by_weight() {
+        let mut totals = HashMap::new();
+        bump_ref_by!(totals, "a", 1.5);
+        bump_ref_by!(totals, "b", 2.0);
+
+        let mut other = HashMap::new();
+        bump_ref_by!(other, "a", 0.5);
+        bump_ref_by!(other, "c", 1.0);
+
+        merge_by_weight_into!(totals, other);
+        assert_eq!(weight_ref!(totals, "a"), 2.0);
+        assert_eq!(weight_ref!(totals, "b"), 2.0);
+        assert_eq!(weight_ref!(totals, "c"), 1.0);
+
+        subtract_by_weight_from!(totals, other);
+        assert_eq!(weight_ref!(totals, "a"), 1.5);
+        assert!(!totals.contains_key("c"));
+    }
+
     #[test]
     fn test_hist() {
         let mut hist = HashHistogram::new();
@@ -537,4 +999,46 @@ mod tests {
         assert_eq!(2, hist.mode().unwrap());
         assert_eq!(zeros + ones + twos, hist.total_count());
     }
+
+    #[test]
+    fn test_hist_collection_traits() {
+        let words = ["a", "b", "a", "b", "b"];
+        let mut hist: HashHistogram<&str> = words.iter().collect();
+        assert_eq!(hist.count(&"a"), 2);
+        assert_eq!(hist.count(&"b"), 3);
+
+        hist.extend(["a", "c"]);
+        assert_eq!(hist.count(&"a"), 3);
+        assert_eq!(hist.count(&"c"), 1);
+
+        let total: usize = (&hist).into_iter().map(|(_, count)| count).sum();
+        assert_eq!(total, hist.total_count());
+
+        let default_hist: HashHistogram<&str> = Default::default();
+        assert_eq!(default_hist.total_count(), 0);
+    }
+
+    #[test]
+    fn test_hist_with_hasher() {
+        use std::collections::hash_map::RandomState;
+
+        histogram_struct!{CustomHashHistogram, CustomHashHistKey, HashMap, HashSet, Iter, Hash, RandomState}
+
+        let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::new();
+        hist.bump(&1);
+        hist.bump(&1);
+        assert_eq!(hist.count(&1), 2);
+
+        let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::with_capacity(16);
+        hist.bump(&2);
+        assert_eq!(hist.count(&2), 1);
+
+        let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::with_hasher(RandomState::new());
+        hist.bump(&3);
+        assert_eq!(hist.count(&3), 1);
+
+        let mut hist: CustomHashHistogram<i32> = CustomHashHistogram::with_capacity_and_hasher(8, RandomState::new());
+        hist.bump(&4);
+        assert_eq!(hist.count(&4), 1);
+    }
 }